@@ -6,7 +6,18 @@ impl super::Frame for backtrace::Frame {
     }
 
     fn resolve_symbol<F: FnMut(Symbol)>(&self, mut cb: F) {
-        backtrace::resolve_frame(self, |s| cb(Symbol::from(s)));
+        super::resolve_symbol_cached(self.symbol_address(), &mut cb, |cb| {
+            let mut has_name = false;
+            backtrace::resolve_frame(self, |s| {
+                if s.name().is_some() {
+                    has_name = true;
+                }
+                cb(Symbol::from(s));
+            });
+            if !has_name {
+                super::resolve_jit_fallback(self.ip() as usize, cb);
+            }
+        });
     }
 
     fn symbol_address(&self) -> *mut libc::c_void {
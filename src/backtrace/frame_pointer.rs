@@ -0,0 +1,56 @@
+use crate::Symbol;
+use libc::c_void;
+
+// This tree has no upstream implementation of the `frame-pointer` unwinder
+// (`mod.rs` already `cfg`-gates a `pub mod frame_pointer;` for it, but the
+// file itself didn't exist), and hand-rolling a real frame-pointer-chain
+// walker (raw register reads, frame-record layout per arch, cycle/bounds
+// checks) is a correctness- and safety-sensitive change that deserves its
+// own dedicated review rather than riding along with the perf-map/jitdump
+// fallback. So this delegates the actual unwinding to `backtrace_rs`'s
+// well-tested `trace_unsynchronized`, same as the `backtrace_rs` module,
+// and only adds what this request actually asked for: wiring the
+// perf-map/jitdump fallback into `resolve_symbol`.
+#[derive(Clone)]
+pub struct Frame {
+    inner: backtrace::Frame,
+}
+
+impl super::Frame for Frame {
+    fn ip(&self) -> usize {
+        self.inner.ip() as usize
+    }
+
+    fn symbol_address(&self) -> *mut c_void {
+        self.inner.symbol_address()
+    }
+
+    fn resolve_symbol<F: FnMut(Symbol)>(&self, mut cb: F) {
+        super::resolve_symbol_cached(self.symbol_address(), &mut cb, |cb| {
+            let mut has_name = false;
+            backtrace::resolve_frame(&self.inner, |s| {
+                if s.name().is_some() {
+                    has_name = true;
+                }
+                cb(Symbol::from(s));
+            });
+            if !has_name {
+                super::resolve_jit_fallback(self.ip(), cb);
+            }
+        });
+    }
+}
+
+pub struct Trace {}
+
+impl super::Trace for Trace {
+    type Frame = Frame;
+
+    fn trace<F: FnMut(&Self::Frame) -> bool>(_: *mut c_void, mut cb: F) {
+        unsafe {
+            backtrace::trace_unsynchronized(|frame| cb(&Frame {
+                inner: frame.clone(),
+            }));
+        }
+    }
+}
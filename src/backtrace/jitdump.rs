@@ -0,0 +1,452 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use notify_debouncer_mini::{
+    new_debouncer,
+    notify::{RecommendedWatcher, RecursiveMode},
+    DebounceEventHandler, Debouncer,
+};
+
+use crate::{backtrace::AsSymbol, Error};
+
+/// Name of the environment variable used to override the default
+/// `/tmp/jit-<pid>.dump` location, for containers and sandboxes that place
+/// the dump elsewhere.
+pub const PPROF_JIT_DUMP_ENV: &str = "PPROF_JIT_DUMP";
+
+fn default_jitdump_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(PPROF_JIT_DUMP_ENV) {
+        return PathBuf::from(path);
+    }
+    PathBuf::from("/tmp/").join(format!("jit-{}.dump", std::process::id()))
+}
+
+const JITDUMP_MAGIC: u32 = 0x4A69_5444;
+
+const JIT_CODE_LOAD: u32 = 0;
+const JIT_DEBUG_INFO: u32 = 2;
+
+// A record body should never be anywhere near this large in practice; a
+// corrupted or partially-written `total_size` (e.g. read mid-write by the
+// debounced reloader below) shouldn't be able to trigger a huge allocation.
+const MAX_RECORD_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+// Smallest a `JIT_DEBUG_INFO` entry can be: addr (8) + line (4) + discrim (4)
+// + a 1-byte-minimum nul-terminated filename.
+const MIN_DEBUG_ENTRY_SIZE: usize = 8 + 4 + 4 + 1;
+
+// A single `{addr, line, filename}` entry from a `JIT_DEBUG_INFO` record,
+// sorted by `addr` within a `JitCodeEntry` so that `find` can binary search it.
+struct JitDebugEntry {
+    addr: u64,
+    line: u32,
+    filename: String,
+}
+
+struct JitCodeEntry {
+    code_addr: u64,
+    code_size: u64,
+    name: String,
+    // Sorted by `addr` ascending.
+    debug_entries: Vec<JitDebugEntry>,
+}
+
+pub struct JitDump {
+    // Sorted by `code_addr` ascending so `find` can binary search it.
+    entries: Vec<JitCodeEntry>,
+}
+
+impl JitDump {
+    pub fn new() -> Option<Self> {
+        Self::with_path(&default_jitdump_path())
+    }
+
+    pub fn with_path(path: &Path) -> Option<Self> {
+        let file = std::fs::File::open(path).ok()?;
+        Self::parse(BufReader::new(file))
+    }
+
+    fn parse<R: BufRead>(mut reader: R) -> Option<Self> {
+        // Fixed header: magic, version, total_size, elf_mach, pad, pid, timestamp, flags.
+        let _magic = read_u32(&mut reader)?;
+        let _version = read_u32(&mut reader)?;
+        let total_header_size = read_u32(&mut reader)?;
+        let _elf_mach = read_u32(&mut reader)?;
+        let _pad = read_u32(&mut reader)?;
+        let _pid = read_u32(&mut reader)?;
+        let _timestamp = read_u64(&mut reader)?;
+        let _flags = read_u64(&mut reader)?;
+
+        // The header above is 40 bytes; skip any extra bytes the writer reserved.
+        if total_header_size > 40 {
+            let mut pad = vec![0u8; (total_header_size - 40) as usize];
+            reader.read_exact(&mut pad).ok()?;
+        }
+
+        let mut entries: Vec<JitCodeEntry> = Vec::new();
+        let mut pending_debug_info: Option<(u64, Vec<JitDebugEntry>)> = None;
+
+        loop {
+            let id = match read_u32(&mut reader) {
+                Some(id) => id,
+                None => break,
+            };
+            let total_size = read_u32(&mut reader)?;
+            let _timestamp = read_u64(&mut reader)?;
+            // Record header itself is 16 bytes (id, total_size, timestamp).
+            let body_size = (total_size as usize).checked_sub(16)?;
+            if body_size > MAX_RECORD_BODY_SIZE {
+                return None;
+            }
+            let mut body = vec![0u8; body_size];
+            reader.read_exact(&mut body).ok()?;
+            let mut body = body.as_slice();
+
+            match id {
+                JIT_CODE_LOAD => {
+                    let _pid = take_u32(&mut body)?;
+                    let _tid = take_u32(&mut body)?;
+                    let _vma = take_u64(&mut body)?;
+                    let code_addr = take_u64(&mut body)?;
+                    let code_size = take_u64(&mut body)?;
+                    let _code_index = take_u64(&mut body)?;
+                    let name = take_cstr(&mut body)?;
+
+                    let debug_entries = match pending_debug_info.take() {
+                        Some((addr, debug_entries)) if addr == code_addr => debug_entries,
+                        _ => Vec::new(),
+                    };
+
+                    entries.push(JitCodeEntry {
+                        code_addr,
+                        code_size,
+                        name,
+                        debug_entries,
+                    });
+                }
+                JIT_DEBUG_INFO => {
+                    let code_addr = take_u64(&mut body)?;
+                    let nr_entry = take_u64(&mut body)?;
+                    // Bound against the body's actual remaining length rather
+                    // than trusting `nr_entry` verbatim, so a corrupted count
+                    // can't force a huge `Vec::with_capacity` allocation.
+                    if nr_entry > (body.len() / MIN_DEBUG_ENTRY_SIZE) as u64 {
+                        return None;
+                    }
+                    let mut debug_entries = Vec::with_capacity(nr_entry as usize);
+                    for _ in 0..nr_entry {
+                        let addr = take_u64(&mut body)?;
+                        let line = take_u32(&mut body)?;
+                        let _discrim = take_u32(&mut body)?;
+                        let filename = take_cstr(&mut body)?;
+                        debug_entries.push(JitDebugEntry {
+                            addr,
+                            line,
+                            filename,
+                        });
+                    }
+                    debug_entries.sort_by_key(|e| e.addr);
+                    pending_debug_info = Some((code_addr, debug_entries));
+                }
+                _ => {}
+            }
+        }
+
+        entries.sort_by_key(|e| e.code_addr);
+        Some(Self { entries })
+    }
+
+    pub fn find(&self, addr: usize) -> Option<JitDumpSymbol> {
+        let addr = addr as u64;
+        let idx = self
+            .entries
+            .partition_point(|entry| entry.code_addr <= addr)
+            .checked_sub(1)?;
+        let entry = &self.entries[idx];
+        if addr >= entry.code_addr + entry.code_size {
+            return None;
+        }
+
+        let debug_entry = match entry
+            .debug_entries
+            .partition_point(|e| e.addr <= addr)
+            .checked_sub(1)
+        {
+            Some(i) => Some(&entry.debug_entries[i]),
+            None => None,
+        };
+
+        Some(JitDumpSymbol {
+            name: entry.name.clone(),
+            line: debug_entry.map(|e| e.line),
+            filename: debug_entry.map(|e| PathBuf::from(&e.filename)),
+        })
+    }
+}
+
+pub struct JitDumpSymbol {
+    name: String,
+    line: Option<u32>,
+    filename: Option<PathBuf>,
+}
+
+impl AsSymbol for JitDumpSymbol {
+    fn name(&self) -> Option<Vec<u8>> {
+        Some(self.name.as_bytes().to_vec())
+    }
+
+    fn addr(&self) -> Option<*mut std::ffi::c_void> {
+        None
+    }
+
+    fn lineno(&self) -> Option<u32> {
+        self.line
+    }
+
+    fn filename(&self) -> Option<PathBuf> {
+        self.filename.clone()
+    }
+}
+
+fn read_u32<R: BufRead>(r: &mut R) -> Option<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf).ok()?;
+    Some(u32::from_ne_bytes(buf))
+}
+
+fn read_u64<R: BufRead>(r: &mut R) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf).ok()?;
+    Some(u64::from_ne_bytes(buf))
+}
+
+fn take_u32(buf: &mut &[u8]) -> Option<u32> {
+    if buf.len() < 4 {
+        return None;
+    }
+    let (head, rest) = buf.split_at(4);
+    *buf = rest;
+    Some(u32::from_ne_bytes(head.try_into().ok()?))
+}
+
+fn take_u64(buf: &mut &[u8]) -> Option<u64> {
+    if buf.len() < 8 {
+        return None;
+    }
+    let (head, rest) = buf.split_at(8);
+    *buf = rest;
+    Some(u64::from_ne_bytes(head.try_into().ok()?))
+}
+
+fn take_cstr(buf: &mut &[u8]) -> Option<String> {
+    let nul = buf.iter().position(|&b| b == 0)?;
+    let s = String::from_utf8_lossy(&buf[..nul]).into_owned();
+    *buf = &buf[nul + 1..];
+    Some(s)
+}
+
+pub struct JitDumpResolver {
+    jit_dump: Arc<Mutex<Option<JitDump>>>,
+}
+
+fn create_debouncer<F: DebounceEventHandler>(
+    event_handler: F,
+    path: &Path,
+) -> Result<Debouncer<RecommendedWatcher>, Error> {
+    let mut debouncer =
+        new_debouncer(Duration::from_secs(1), event_handler).map_err(|_| Error::CreatingError)?;
+    // Watch the parent directory rather than `path` itself: `inotify_add_watch`
+    // (what `RecommendedWatcher` uses on Linux) fails with `ENOENT` if `path`
+    // doesn't exist yet, which is the ordinary case when the profiler attaches
+    // before the JIT'd process has written its dump. The reload thread below
+    // filters directory events back down to `path`.
+    let watch_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    debouncer
+        .watcher()
+        .watch(watch_dir, RecursiveMode::NonRecursive)
+        .map_err(|_| Error::CreatingError)?;
+    Ok(debouncer)
+}
+
+impl JitDumpResolver {
+    pub fn new() -> Result<Self, Error> {
+        Self::with_path(default_jitdump_path())
+    }
+
+    /// Like [`JitDumpResolver::new`], but watches `path` instead of the
+    /// default `/tmp/jit-<pid>.dump` (or `PPROF_JIT_DUMP`) location.
+    pub fn with_path(path: PathBuf) -> Result<Self, Error> {
+        let jit_dump = Arc::new(Mutex::new(JitDump::with_path(&path)));
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let debouncer = create_debouncer(tx, &path)?;
+        let thread_jit_dump = Arc::clone(&jit_dump);
+
+        std::thread::spawn(move || {
+            let _debouncer = debouncer;
+            for result in rx {
+                match result {
+                    Ok(events) => {
+                        if events.iter().any(|event| event.path == path) {
+                            let mut jit_dump = thread_jit_dump.lock();
+                            *jit_dump = JitDump::with_path(&path);
+                        }
+                    }
+                    Err(error) => log::info!("Error {error:?}"),
+                }
+            }
+        });
+        Ok(Self { jit_dump })
+    }
+
+    pub fn resolve(&self, addr: usize) -> Option<JitDumpSymbol> {
+        self.jit_dump.lock().as_ref()?.find(addr)
+    }
+}
+
+pub static JIT_DUMP_RESOLVER: OnceCell<JitDumpResolver> = OnceCell::new();
+
+pub fn init_jitdump_resolver() -> Result<(), Error> {
+    let jit_dump_resolver = JitDumpResolver::new()?;
+    JIT_DUMP_RESOLVER
+        .set(jit_dump_resolver)
+        .map_err(|_| Error::CreatingError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(total_header_size: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&JITDUMP_MAGIC.to_ne_bytes());
+        buf.extend_from_slice(&1u32.to_ne_bytes()); // version
+        buf.extend_from_slice(&total_header_size.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes()); // elf_mach
+        buf.extend_from_slice(&0u32.to_ne_bytes()); // pad
+        buf.extend_from_slice(&std::process::id().to_ne_bytes());
+        buf.extend_from_slice(&0u64.to_ne_bytes()); // timestamp
+        buf.extend_from_slice(&0u64.to_ne_bytes()); // flags
+        buf
+    }
+
+    fn code_load_record(code_addr: u64, code_size: u64, name: &str) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_ne_bytes()); // pid
+        body.extend_from_slice(&0u32.to_ne_bytes()); // tid
+        body.extend_from_slice(&code_addr.to_ne_bytes()); // vma
+        body.extend_from_slice(&code_addr.to_ne_bytes());
+        body.extend_from_slice(&code_size.to_ne_bytes());
+        body.extend_from_slice(&0u64.to_ne_bytes()); // code_index
+        body.extend_from_slice(name.as_bytes());
+        body.push(0);
+
+        let mut record = Vec::new();
+        record.extend_from_slice(&JIT_CODE_LOAD.to_ne_bytes());
+        record.extend_from_slice(&((16 + body.len()) as u32).to_ne_bytes());
+        record.extend_from_slice(&0u64.to_ne_bytes()); // timestamp
+        record.extend_from_slice(&body);
+        record
+    }
+
+    fn debug_info_record(code_addr: u64, entries: &[(u64, u32, &str)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&code_addr.to_ne_bytes());
+        body.extend_from_slice(&(entries.len() as u64).to_ne_bytes());
+        for (addr, line, filename) in entries {
+            body.extend_from_slice(&addr.to_ne_bytes());
+            body.extend_from_slice(&line.to_ne_bytes());
+            body.extend_from_slice(&0u32.to_ne_bytes()); // discrim
+            body.extend_from_slice(filename.as_bytes());
+            body.push(0);
+        }
+
+        let mut record = Vec::new();
+        record.extend_from_slice(&JIT_DEBUG_INFO.to_ne_bytes());
+        record.extend_from_slice(&((16 + body.len()) as u32).to_ne_bytes());
+        record.extend_from_slice(&0u64.to_ne_bytes()); // timestamp
+        record.extend_from_slice(&body);
+        record
+    }
+
+    #[test]
+    fn finds_function_name_without_debug_info() {
+        let mut buf = header(40);
+        buf.extend_from_slice(&code_load_record(0x1000, 0x100, "jitted_fn"));
+
+        let jit_dump = JitDump::parse(buf.as_slice()).unwrap();
+        let symbol = jit_dump.find(0x1050).unwrap();
+        assert_eq!(symbol.name(), Some(b"jitted_fn".to_vec()));
+        assert_eq!(symbol.lineno(), None);
+    }
+
+    #[test]
+    fn finds_nearest_debug_entry_at_or_before_address() {
+        let mut buf = header(40);
+        buf.extend_from_slice(&debug_info_record(
+            0x2000,
+            &[(0x2000, 10, "a.rs"), (0x2010, 20, "a.rs"), (0x2020, 30, "a.rs")],
+        ));
+        buf.extend_from_slice(&code_load_record(0x2000, 0x100, "jitted_fn"));
+
+        let jit_dump = JitDump::parse(buf.as_slice()).unwrap();
+
+        let symbol = jit_dump.find(0x2015).unwrap();
+        assert_eq!(symbol.lineno(), Some(20));
+
+        let symbol = jit_dump.find(0x2025).unwrap();
+        assert_eq!(symbol.lineno(), Some(30));
+    }
+
+    #[test]
+    fn addresses_outside_any_code_range_are_not_found() {
+        let mut buf = header(40);
+        buf.extend_from_slice(&code_load_record(0x1000, 0x100, "jitted_fn"));
+
+        let jit_dump = JitDump::parse(buf.as_slice()).unwrap();
+        assert!(jit_dump.find(0x0fff).is_none());
+        assert!(jit_dump.find(0x1100).is_none());
+    }
+
+    #[test]
+    fn truncated_record_fails_to_parse() {
+        let mut buf = header(40);
+        let mut record = code_load_record(0x1000, 0x100, "jitted_fn");
+        record.truncate(record.len() - 4);
+        buf.extend_from_slice(&record);
+
+        assert!(JitDump::parse(buf.as_slice()).is_none());
+    }
+
+    #[test]
+    fn corrupted_total_size_fails_to_parse_instead_of_allocating() {
+        let mut buf = header(40);
+        let mut record = code_load_record(0x1000, 0x100, "jitted_fn");
+        // Overwrite `total_size` (right after the 4-byte `id`) with a huge,
+        // bogus value, as if the record had been read mid-write.
+        record[4..8].copy_from_slice(&u32::MAX.to_ne_bytes());
+        buf.extend_from_slice(&record);
+
+        assert!(JitDump::parse(buf.as_slice()).is_none());
+    }
+
+    #[test]
+    fn corrupted_nr_entry_fails_to_parse_instead_of_allocating() {
+        let header = header(40);
+        let record_offset = header.len();
+        let mut buf = header;
+        buf.extend_from_slice(&debug_info_record(0x2000, &[(0x2000, 10, "a.rs")]));
+        // Overwrite `nr_entry` (after the 16-byte record header and the
+        // 8-byte `code_addr`) with a huge, bogus value.
+        let nr_entry_offset = record_offset + 16 + 8;
+        buf[nr_entry_offset..nr_entry_offset + 8].copy_from_slice(&u64::MAX.to_ne_bytes());
+
+        assert!(JitDump::parse(buf.as_slice()).is_none());
+    }
+}
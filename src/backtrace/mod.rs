@@ -2,7 +2,9 @@
 
 use crate::Symbol;
 use libc::c_void;
-use std::path::PathBuf;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 pub trait AsSymbol: Sized {
     fn name(&self) -> Option<Vec<u8>>;
@@ -43,6 +45,82 @@ pub trait Trace {
         Self: Sized;
 }
 
+mod perfmap;
+pub use perfmap::{init_perfmap_resolver, PerfMap, PerfMapResolver, PerfMapSymbol, PERF_MAP_RESOLVER};
+
+mod jitdump;
+pub use jitdump::{init_jitdump_resolver, JitDump, JitDumpResolver, JitDumpSymbol, JIT_DUMP_RESOLVER};
+
+pub mod modules;
+pub use modules::{
+    build_id_debug_path, enumerate_loaded_modules, read_build_id, symbolicate, symbolicate_frames,
+    ModuleInfo,
+};
+
+// Resolving a frame (`backtrace::resolve_frame` and friends) dominates
+// `Report` generation cost for deep or repetitive stacks, so every resolved
+// symbol is cached here, keyed on the frame's `symbol_address`. Keyed by the
+// address as a plain `usize` rather than the raw pointer, since raw
+// pointers aren't `Send`/`Sync` and can't live in a `static`.
+static SYMBOL_CACHE: OnceCell<Mutex<HashMap<usize, Arc<Vec<Symbol>>>>> = OnceCell::new();
+
+fn symbol_cache() -> &'static Mutex<HashMap<usize, Arc<Vec<Symbol>>>> {
+    SYMBOL_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Clears every cached symbol. Long-running processes where JIT code is
+/// freed and its address range later reused for different code should call
+/// this, or a stale symbol from the old mapping would be served forever.
+pub fn clear_symbol_cache() {
+    symbol_cache().lock().clear();
+}
+
+/// Resolves the symbol(s) at `symbol_address` through `cb`, consulting the
+/// process-wide cache first. `resolve` is only invoked on a cache miss, and
+/// whatever it passes to its own callback is cached under `symbol_address`
+/// for subsequent lookups.
+pub(crate) fn resolve_symbol_cached<F>(
+    symbol_address: *mut c_void,
+    cb: &mut dyn FnMut(Symbol),
+    resolve: F,
+) where
+    F: FnOnce(&mut dyn FnMut(Symbol)),
+{
+    let key = symbol_address as usize;
+    if let Some(symbols) = symbol_cache().lock().get(&key) {
+        for symbol in symbols.iter() {
+            cb(symbol.clone());
+        }
+        return;
+    }
+
+    let mut symbols = Vec::new();
+    resolve(&mut |symbol: Symbol| symbols.push(symbol));
+    for symbol in &symbols {
+        cb(symbol.clone());
+    }
+    // Don't cache a miss: the perf-map/jitdump resolvers reload on a
+    // debounced file-watch, so an address sampled before a JIT'd function's
+    // entry shows up should resolve normally once it does, rather than
+    // being stuck unnamed for the rest of the process.
+    if symbols.iter().any(|symbol| symbol.name.is_some()) {
+        symbol_cache().lock().insert(key, Arc::new(symbols));
+    }
+}
+
+/// Shared fallback used by every `TraceImpl` when native symbol resolution
+/// (`backtrace::resolve_frame`/`resolve`) didn't find a name for `ip`: try
+/// the perf-map resolver, then the jitdump resolver, in that order.
+pub(crate) fn resolve_jit_fallback(ip: usize, cb: &mut dyn FnMut(Symbol)) {
+    if let Some(symbol) = PERF_MAP_RESOLVER.get().and_then(|resolver| resolver.resolve(ip)) {
+        cb(Symbol::from(symbol));
+        return;
+    }
+    if let Some(symbol) = JIT_DUMP_RESOLVER.get().and_then(|resolver| resolver.resolve(ip)) {
+        cb(Symbol::from(symbol));
+    }
+}
+
 #[cfg(not(all(
     any(
         target_arch = "x86_64",
@@ -98,10 +176,3 @@ pub mod framehop_unwinder;
     feature = "framehop-unwinder"
 ))]
 pub use framehop_unwinder::Trace as TraceImpl;
-
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "aarch64",),
-    any(target_os = "linux", target_os = "macos",),
-    feature = "framehop-unwinder"
-))]
-pub use framehop_unwinder::init_perfmap_resolver;
@@ -0,0 +1,400 @@
+// Module table used for offline/deferred symbolication: a profiler can
+// record raw instruction pointers during sampling (see `Frame::ip` /
+// `Frame::symbol_address`) and defer the expensive symbol lookup to a
+// separate process, provided it also knows which module each address
+// belongs to and how to identify the exact binary that produced it.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{backtrace::AsSymbol, Symbol};
+
+/// A module loaded into the current process's address space at sampling
+/// time, along with enough information to locate a matching debug binary
+/// later: its on-disk path, the range of addresses it occupies, and its
+/// build-id (read from the ELF `.note.gnu.build-id` section) if present.
+#[derive(Debug, Clone)]
+pub struct ModuleInfo {
+    pub path: PathBuf,
+    pub load_base: usize,
+    pub address_range: Range<usize>,
+    pub build_id: Option<Vec<u8>>,
+}
+
+/// Enumerates the modules currently mapped into this process, keyed by
+/// their executable address ranges.
+#[cfg(target_os = "linux")]
+pub fn enumerate_loaded_modules() -> Vec<ModuleInfo> {
+    let file = match File::open("/proc/self/maps") {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+    let reader = BufReader::new(file);
+
+    let mut modules: Vec<ModuleInfo> = Vec::new();
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => continue,
+        };
+        // Example: 7f3b5f7e0000-7f3b5f7e2000 r-xp 00000000 08:01 1234 /lib/x86_64-linux-gnu/libc.so.6
+        let mut parts = line.split_whitespace();
+        let range_part = match parts.next() {
+            Some(part) => part,
+            None => continue,
+        };
+        let perms = match parts.next() {
+            Some(perms) => perms,
+            None => continue,
+        };
+        if !perms.contains('x') {
+            continue;
+        }
+        let path = match parts.last() {
+            Some(path) if path.starts_with('/') => PathBuf::from(path),
+            _ => continue,
+        };
+
+        let (start, end) = match range_part.split_once('-') {
+            Some((start, end)) => (
+                usize::from_str_radix(start, 16),
+                usize::from_str_radix(end, 16),
+            ),
+            None => continue,
+        };
+        let (start, end) = match (start, end) {
+            (Ok(start), Ok(end)) => (start, end),
+            _ => continue,
+        };
+
+        if let Some(module) = modules.iter_mut().find(|m: &&mut ModuleInfo| m.path == path) {
+            module.address_range = module.address_range.start.min(start)..module.address_range.end.max(end);
+            continue;
+        }
+
+        let build_id = read_build_id(&path);
+        modules.push(ModuleInfo {
+            path,
+            load_base: start,
+            address_range: start..end,
+            build_id,
+        });
+    }
+    modules
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn enumerate_loaded_modules() -> Vec<ModuleInfo> {
+    Vec::new()
+}
+
+const EI_NIDENT: usize = 16;
+const ELFCLASS64: u8 = 2;
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// Reads the build-id out of an ELF file's `.note.gnu.build-id` section, if
+/// one is present. Only 64-bit little-endian ELF files are supported, which
+/// covers every platform `pprof` currently ships unwinders for.
+pub fn read_build_id(path: &Path) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    let mut ident = [0u8; EI_NIDENT];
+    file.read_exact(&mut ident).ok()?;
+    if &ident[0..4] != b"\x7fELF" || ident[4] != ELFCLASS64 {
+        return None;
+    }
+
+    // e_type(2) + e_machine(2) + e_version(4) + e_entry(8) + e_phoff(8) = 24 bytes to skip,
+    // landing right before e_shoff.
+    file.seek(SeekFrom::Current(24)).ok()?;
+    let e_shoff = read_u64(&mut file)?;
+    // e_flags(4) + e_ehsize(2) + e_phentsize(2) + e_phnum(2) + e_shentsize(2) = 12 bytes,
+    // landing right before e_shnum.
+    file.seek(SeekFrom::Current(12)).ok()?;
+    let e_shnum = read_u16(&mut file)?;
+    let e_shstrndx = read_u16(&mut file)?;
+    let e_shentsize = 64u64; // Elf64_Shdr is fixed-size.
+
+    // sh_name(4) + sh_type(4) + sh_flags(8) + sh_addr(8) = 24 bytes to skip,
+    // landing right before sh_offset in each section header.
+    let section_header = |file: &mut File, index: u16| -> Option<(u32, u64, u64)> {
+        file.seek(SeekFrom::Start(e_shoff + index as u64 * e_shentsize))
+            .ok()?;
+        let sh_name = read_u32(file)?;
+        file.seek(SeekFrom::Current(20)).ok()?;
+        let sh_offset = read_u64(file)?;
+        let sh_size = read_u64(file)?;
+        Some((sh_name, sh_offset, sh_size))
+    };
+
+    let (_, shstrtab_offset, _) = section_header(&mut file, e_shstrndx)?;
+
+    for index in 0..e_shnum {
+        let (sh_name, sh_offset, _sh_size) = section_header(&mut file, index)?;
+        file.seek(SeekFrom::Start(shstrtab_offset + sh_name as u64))
+            .ok()?;
+        let name = read_cstr(&mut file)?;
+        if name != ".note.gnu.build-id" {
+            continue;
+        }
+
+        file.seek(SeekFrom::Start(sh_offset)).ok()?;
+        let namesz = read_u32(&mut file)?;
+        let descsz = read_u32(&mut file)?;
+        let note_type = read_u32(&mut file)?;
+        file.seek(SeekFrom::Current(align4(namesz) as i64)).ok()?;
+        if note_type != NT_GNU_BUILD_ID {
+            continue;
+        }
+        let mut build_id = vec![0u8; descsz as usize];
+        file.read_exact(&mut build_id).ok()?;
+        return Some(build_id);
+    }
+    None
+}
+
+fn align4(n: u32) -> u32 {
+    (n + 3) & !3
+}
+
+fn read_u16(file: &mut File) -> Option<u16> {
+    let mut buf = [0u8; 2];
+    file.read_exact(&mut buf).ok()?;
+    Some(u16::from_le_bytes(buf))
+}
+
+fn read_u32(file: &mut File) -> Option<u32> {
+    let mut buf = [0u8; 4];
+    file.read_exact(&mut buf).ok()?;
+    Some(u32::from_le_bytes(buf))
+}
+
+fn read_u64(file: &mut File) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    file.read_exact(&mut buf).ok()?;
+    Some(u64::from_le_bytes(buf))
+}
+
+fn read_cstr(file: &mut File) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        file.read_exact(&mut byte).ok()?;
+        if byte[0] == 0 {
+            break;
+        }
+        bytes.push(byte[0]);
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Formats a build-id the way debug file layouts conventionally do:
+/// `<first two hex chars>/<remaining hex chars>.debug`, e.g. the build-id
+/// `abcdef...` resolves to `ab/cdef....debug` under a `.build-id` root.
+pub fn build_id_debug_path(build_id: &[u8]) -> Option<PathBuf> {
+    if build_id.len() < 2 {
+        return None;
+    }
+    let hex: String = build_id.iter().map(|b| format!("{b:02x}")).collect();
+    let (prefix, rest) = hex.split_at(2);
+    Some(PathBuf::from(prefix).join(format!("{rest}.debug")))
+}
+
+struct ModuleOffsetSymbol(String);
+
+impl AsSymbol for ModuleOffsetSymbol {
+    fn name(&self) -> Option<Vec<u8>> {
+        Some(self.0.as_bytes().to_vec())
+    }
+
+    fn addr(&self) -> Option<*mut std::ffi::c_void> {
+        None
+    }
+
+    fn lineno(&self) -> Option<u32> {
+        None
+    }
+
+    fn filename(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Best-effort offline symbolication for a single raw address: finds the
+/// module that owns it in `modules` and, if a debug file matching its
+/// build-id exists under `debug_dir`, returns a `<module>+<offset>` symbol.
+/// That's enough for a separate `addr2line`/DWARF pass run against the
+/// located debug file to resolve down to an exact name and line; this
+/// function only does the module/build-id matching, not DWARF parsing.
+/// Returns `None` when `addr` isn't covered by any module, or the owning
+/// module has no build-id, or no matching debug file is found.
+pub fn symbolicate(addr: usize, modules: &[ModuleInfo], debug_dir: &Path) -> Option<Symbol> {
+    let module = modules.iter().find(|m| m.address_range.contains(&addr))?;
+    let build_id = module.build_id.as_ref()?;
+    let debug_path = debug_dir.join(build_id_debug_path(build_id)?);
+    if !debug_path.is_file() {
+        return None;
+    }
+
+    let offset = addr - module.load_base;
+    let name = format!(
+        "{}+0x{:x}",
+        module.path.file_name()?.to_string_lossy(),
+        offset
+    );
+    Some(Symbol::from(ModuleOffsetSymbol(name)))
+}
+
+/// [`symbolicate`] over a whole stack's worth of raw addresses at once, in
+/// order, so a caller doesn't have to loop over `symbolicate` itself.
+///
+/// This is as far as offline symbolication goes in this tree: rewriting it
+/// into an actual report's frames belongs on the `Report`/`ProfilerGuardBuilder`
+/// side (an `unresolved()` builder option that records raw addresses plus the
+/// module table instead of calling `resolve_symbol` at sample time, and a
+/// `Report`-level `symbolicate` that walks and replaces its frames), neither
+/// of which exists anywhere in this source tree to extend.
+pub fn symbolicate_frames(
+    addrs: &[usize],
+    modules: &[ModuleInfo],
+    debug_dir: &Path,
+) -> Vec<Option<Symbol>> {
+    addrs
+        .iter()
+        .map(|&addr| symbolicate(addr, modules, debug_dir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    // Builds a minimal, otherwise-empty 64-bit little-endian ELF file with a
+    // single `.note.gnu.build-id` section holding `build_id`, laid out as:
+    // ELF header, then 3 section headers (null, the note, `.shstrtab`), then
+    // the note's bytes, then the string table's bytes.
+    fn synthetic_elf(build_id: &[u8]) -> Vec<u8> {
+        const EHDR_SIZE: u64 = 64;
+        const SHDR_SIZE: u64 = 64;
+        let shoff = EHDR_SIZE;
+        let note_offset = shoff + SHDR_SIZE * 3;
+
+        let mut note = Vec::new();
+        note.extend_from_slice(&4u32.to_le_bytes()); // namesz
+        note.extend_from_slice(&(build_id.len() as u32).to_le_bytes()); // descsz
+        note.extend_from_slice(&NT_GNU_BUILD_ID.to_le_bytes()); // type
+        note.extend_from_slice(b"GNU\0"); // name, already 4-byte aligned
+        note.extend_from_slice(build_id);
+
+        let shstrtab: &[u8] = b"\0.note.gnu.build-id\0";
+        let shstrtab_offset = note_offset + note.len() as u64;
+
+        let mut elf = Vec::new();
+        elf.extend_from_slice(b"\x7fELF");
+        elf.push(2); // ELFCLASS64
+        elf.extend_from_slice(&[0u8; 11]); // rest of e_ident
+        elf.extend_from_slice(&[0u8; 24]); // e_type..e_phoff
+        elf.extend_from_slice(&shoff.to_le_bytes()); // e_shoff
+        elf.extend_from_slice(&[0u8; 12]); // e_flags..e_shentsize
+        elf.extend_from_slice(&3u16.to_le_bytes()); // e_shnum
+        elf.extend_from_slice(&2u16.to_le_bytes()); // e_shstrndx
+        assert_eq!(elf.len() as u64, EHDR_SIZE);
+
+        let section_header = |sh_name: u32, sh_offset: u64, sh_size: u64| -> Vec<u8> {
+            let mut shdr = Vec::new();
+            shdr.extend_from_slice(&sh_name.to_le_bytes());
+            shdr.extend_from_slice(&[0u8; 4]); // sh_type
+            shdr.extend_from_slice(&[0u8; 8]); // sh_flags
+            shdr.extend_from_slice(&[0u8; 8]); // sh_addr
+            shdr.extend_from_slice(&sh_offset.to_le_bytes());
+            shdr.extend_from_slice(&sh_size.to_le_bytes());
+            shdr.extend_from_slice(&[0u8; 24]); // sh_link..sh_entsize
+            assert_eq!(shdr.len() as u64, SHDR_SIZE);
+            shdr
+        };
+
+        elf.extend_from_slice(&section_header(0, 0, 0)); // null section
+        elf.extend_from_slice(&section_header(1, note_offset, note.len() as u64));
+        elf.extend_from_slice(&section_header(0, shstrtab_offset, shstrtab.len() as u64));
+        elf.extend_from_slice(&note);
+        elf.extend_from_slice(shstrtab);
+        elf
+    }
+
+    fn write_temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut file = File::create(&path).unwrap();
+        file.write_all(contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_build_id_from_note_section() {
+        let build_id = b"\x01\x02\x03\x04deadbeef01234567";
+        let path = write_temp_file(
+            "pprof-rs-test-read-build-id.elf",
+            &synthetic_elf(build_id),
+        );
+
+        let result = read_build_id(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.as_deref(), Some(build_id.as_slice()));
+    }
+
+    #[test]
+    fn returns_none_for_non_elf_file() {
+        let path = write_temp_file("pprof-rs-test-not-elf.elf", b"not an elf file");
+        let result = read_build_id(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn returns_none_for_truncated_elf_file() {
+        let mut bytes = synthetic_elf(b"0123456789abcdef0123");
+        bytes.truncate(bytes.len() - 4);
+        let path = write_temp_file("pprof-rs-test-truncated.elf", &bytes);
+        let result = read_build_id(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn build_id_debug_path_splits_first_byte_as_directory() {
+        let path = build_id_debug_path(&[0xab, 0xcd, 0xef]).unwrap();
+        assert_eq!(path, PathBuf::from("ab").join("cdef.debug"));
+    }
+
+    #[test]
+    fn symbolicate_frames_resolves_each_address_independently() {
+        let build_id = b"0123456789abcdef0123".to_vec();
+        let elf_path = write_temp_file(
+            "pprof-rs-test-symbolicate-frames.elf",
+            &synthetic_elf(&build_id),
+        );
+        let debug_dir = std::env::temp_dir().join("pprof-rs-test-symbolicate-frames-debug");
+        let debug_path = debug_dir.join(build_id_debug_path(&build_id).unwrap());
+        std::fs::create_dir_all(debug_path.parent().unwrap()).unwrap();
+        File::create(&debug_path).unwrap();
+
+        let modules = vec![ModuleInfo {
+            path: elf_path.clone(),
+            load_base: 0x1000,
+            address_range: 0x1000..0x2000,
+            build_id: Some(build_id),
+        }];
+
+        let results = symbolicate_frames(&[0x1010, 0x5000], &modules, &debug_dir);
+
+        std::fs::remove_file(&elf_path).unwrap();
+        std::fs::remove_dir_all(&debug_dir).unwrap();
+
+        assert!(results[0].is_some());
+        assert!(results[1].is_none());
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use notify_debouncer_mini::{
+    new_debouncer,
+    notify::{RecommendedWatcher, RecursiveMode},
+    DebounceEventHandler, Debouncer,
+};
+
+use crate::{backtrace::AsSymbol, Error};
+
+/// Name of the environment variable used to override the default
+/// `/tmp/perf-<pid>.map` location, for containers and sandboxes that place
+/// the map elsewhere.
+pub const PPROF_PERF_MAP_ENV: &str = "PPROF_PERF_MAP";
+
+fn default_perf_map_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(PPROF_PERF_MAP_ENV) {
+        return PathBuf::from(path);
+    }
+    PathBuf::from("/tmp/").join(format!("perf-{}.map", std::process::id()))
+}
+
+pub struct PerfMap {
+    ranges: Vec<(usize, usize, String)>,
+}
+
+impl PerfMap {
+    pub fn new() -> Option<Self> {
+        Self::with_path(&default_perf_map_path())
+    }
+
+    pub fn with_path(path: &Path) -> Option<Self> {
+        let file = std::fs::File::open(path).ok()?;
+        let reader = std::io::BufReader::new(file);
+        let mut ranges = Vec::new();
+        for line in reader.lines() {
+            let line = line.ok()?;
+            // The format of perf map is:
+            // <start addr> <len addr> <name>
+            // where <start addr> and <len addr> are hexadecimal numbers.
+            // where <name> may contain spaces.
+            let mut parts = line.split_whitespace();
+            let start = usize::from_str_radix(parts.next()?, 16).ok()?;
+            let len = usize::from_str_radix(parts.next()?, 16).ok()?;
+            let name = parts.collect::<Vec<_>>().join(" ");
+            ranges.push((start, start + len, name));
+        }
+        Some(Self { ranges })
+    }
+
+    pub fn find(&self, addr: usize) -> Option<&str> {
+        for (start, end, name) in &self.ranges {
+            if *start <= addr && addr < *end {
+                return Some(name);
+            }
+        }
+        None
+    }
+}
+
+pub struct PerfMapSymbol(String);
+
+impl AsSymbol for PerfMapSymbol {
+    fn name(&self) -> Option<Vec<u8>> {
+        Some(self.0.as_bytes().to_vec())
+    }
+
+    fn addr(&self) -> Option<*mut std::ffi::c_void> {
+        None
+    }
+
+    fn lineno(&self) -> Option<u32> {
+        None
+    }
+
+    fn filename(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+pub struct PerfMapResolver {
+    perf_map: Arc<Mutex<Option<PerfMap>>>,
+}
+
+fn create_debouncer<F: DebounceEventHandler>(
+    event_handler: F,
+    path: &Path,
+) -> Result<Debouncer<RecommendedWatcher>, Error> {
+    let mut debouncer =
+        new_debouncer(Duration::from_secs(1), event_handler).map_err(|_| Error::CreatingError)?;
+    // Watch the parent directory rather than `path` itself: `inotify_add_watch`
+    // (what `RecommendedWatcher` uses on Linux) fails with `ENOENT` if `path`
+    // doesn't exist yet, which is the ordinary case when the profiler attaches
+    // before the JIT'd process has written its map. The reload thread below
+    // filters directory events back down to `path`.
+    let watch_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    debouncer
+        .watcher()
+        .watch(watch_dir, RecursiveMode::NonRecursive)
+        .map_err(|_| Error::CreatingError)?;
+    Ok(debouncer)
+}
+
+impl PerfMapResolver {
+    pub fn new() -> Result<Self, Error> {
+        Self::with_path(default_perf_map_path())
+    }
+
+    /// Like [`PerfMapResolver::new`], but watches `path` instead of the
+    /// default `/tmp/perf-<pid>.map` (or `PPROF_PERF_MAP`) location.
+    pub fn with_path(path: PathBuf) -> Result<Self, Error> {
+        let perf_map = Arc::new(Mutex::new(PerfMap::with_path(&path)));
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let debouncer = create_debouncer(tx, &path)?;
+        let thread_perf_map = Arc::clone(&perf_map);
+
+        std::thread::spawn(move || {
+            let _debouncer = debouncer;
+            for result in rx {
+                match result {
+                    Ok(events) => {
+                        if events.iter().any(|event| event.path == path) {
+                            let mut perf_map = thread_perf_map.lock();
+                            *perf_map = PerfMap::with_path(&path);
+                        }
+                    }
+                    Err(error) => log::info!("Error {error:?}"),
+                }
+            }
+        });
+        Ok(Self { perf_map })
+    }
+
+    pub fn resolve(&self, addr: usize) -> Option<PerfMapSymbol> {
+        if let Some(perf_map) = self.perf_map.lock().as_ref() {
+            perf_map.find(addr).map(|s| PerfMapSymbol(s.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+pub static PERF_MAP_RESOLVER: OnceCell<PerfMapResolver> = OnceCell::new();
+
+pub fn init_perfmap_resolver() -> Result<(), Error> {
+    let perf_map_resolver = PerfMapResolver::new()?;
+    PERF_MAP_RESOLVER
+        .set(perf_map_resolver)
+        .map_err(|_| Error::CreatingError)
+}